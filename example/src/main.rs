@@ -15,7 +15,55 @@ fn nonvoid_func(val: bool) -> Option<isize> {
     }
 }
 
+#[frame(title = "enter {user} width={n:>3}", sep = "-", width = 25)]
+fn greet(user: &str, n: usize) {
+    println!("hello, {}!", user);
+}
+
+//  `sep` and `width` fall back to their defaults when omitted, so a frame
+//  only needs a `title` to be usable.
+#[frame(title = "Minimal")]
+fn minimal_func() {
+    println!("only a title was given.");
+}
+
+//  `#[frame]` also works on a whole `impl` block: every method gets its own
+//  guards, with the method name as the default title when none is given.
+struct Counter {
+    count: usize,
+}
+
+#[frame(sep = "=", width = 15)]
+impl Counter {
+    fn new() -> Self {
+        Counter { count: 0 }
+    }
+
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+//  The footer is now printed by a guard's `Drop` impl, so it still shows up
+//  even though this function returns early. `timing = true` additionally
+//  prints how long the function took to run.
+#[frame(title = "Early Return", sep = "-", width = 25, timing = true)]
+fn early_return_func(skip: bool) -> &'static str {
+    if skip {
+        return "skipped";
+    }
+    println!("doing the work.");
+    "done"
+}
+
 fn main() {
     void_func();
     let _: Option<isize> = nonvoid_func(true);
+    greet("ferris", 7);
+    minimal_func();
+
+    let mut counter = Counter::new();
+    counter.increment();
+
+    let _ = early_return_func(true);
 }
@@ -6,9 +6,14 @@ use proc_macro2 as pm2;
 use quote::ToTokens;
 use syn::parse::{Parse, Parser};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 
 struct HeaderConfig {
-    str_opts: Vec<(String, String)>,
+    //  `str_opts` additionally keeps each string literal's own span (rather
+    //  than just `args_span`, the span of the whole argument list) so that
+    //  diagnostics raised later while expanding `title`/`sep` placeholders
+    //  can point at the actual literal that's wrong.
+    str_opts: Vec<(String, String, pm2::Span)>,
     num_opts: Vec<(String, usize)>,
     bin_opts: Vec<(String, bool)>,
 }
@@ -16,7 +21,7 @@ struct HeaderConfig {
 //  The different types of options that we can expect from the user.
 enum Opts {
     Num(usize),
-    Str(String),
+    Str(String, pm2::Span),
     Bin(bool),
 }
 
@@ -32,8 +37,74 @@ impl HeaderConfig {
     }
 }
 
-// TODO: refactor this so that it returns an error.
-fn parse_macro_arguments(args: pm2::TokenStream) -> HeaderConfig {
+//  The only keys `#[frame(...)]` understands. `title` is the sole mandatory
+//  one, and only on a bare function (on an `impl`/`mod` each function's own
+//  name stands in for it) -- `sep`, `width`, `sep_line` and `timing` all fall back to
+//  `DEFAULT_SEP`, `DEFAULT_WIDTH`, `true` and `false` respectively when
+//  omitted.
+const KNOWN_KEYS: &[&str] = &["title", "sep", "width", "sep_line", "timing"];
+
+const DEFAULT_SEP: &str = "-";
+const DEFAULT_WIDTH: usize = 20;
+
+//  The literal type each known key expects, so a value of the wrong type
+//  (e.g. `title = 5`) can be rejected right where it was written instead of
+//  silently landing in the wrong `HeaderConfig` bucket and later looking
+//  like the key was never given at all.
+fn expected_type_for(key: &str) -> &'static str {
+    match key {
+        "title" | "sep" => "str",
+        "width" => "num",
+        "sep_line" | "timing" => "bool",
+        _ => unreachable!("`{}` is not one of KNOWN_KEYS", key),
+    }
+}
+
+//  The literal type an `Opts` value actually carries, for use in the
+//  mismatch message above.
+fn actual_type_of(opt: &Opts) -> &'static str {
+    match opt {
+        Opts::Str(..) => "str",
+        Opts::Num(_) => "num",
+        Opts::Bin(_) => "bool",
+    }
+}
+
+//  Suggests the closest `KNOWN_KEYS` entry to an unrecognized key, so a typo
+//  like `titel` gets a "did you mean `title`?" instead of a flat rejection.
+//  Anything more than 2 edits away is considered unrelated, not a typo.
+fn suggest_known_key(key: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|&known| (known, levenshtein(key, known)))
+        .filter(|(_, dist)| *dist <= 2)
+        .min_by_key(|(_, dist)| *dist)
+        .map(|(known, _)| known)
+}
+
+//  Classic Wagner-Fischer edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut prev_diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == cb {
+                prev_diag
+            } else {
+                1 + prev_diag.min(row[j]).min(row[j + 1])
+            };
+            prev_diag = cur;
+        }
+    }
+    row[b.len()]
+}
+
+fn parse_macro_arguments(args: pm2::TokenStream) -> syn::Result<HeaderConfig> {
     //  Use a `Punctuated` sequence of `syn::ExprAssign` which is basically
     //  things of the form:
     //      ```
@@ -62,37 +133,11 @@ fn parse_macro_arguments(args: pm2::TokenStream) -> HeaderConfig {
     //              also be something else as shown above.
     let expr_parser = Punctuated::<syn::ExprAssign, syn::Token![,]>::parse_terminated;
 
-    //  Consume the argument tokenstream.
-    let expressions = match Parser::parse2(expr_parser, args) {
-        Ok(expressions) => {
-            //  We cannot construct the headers if we do not have at least three
-            //  arguments:
-            //
-            //      1. `title` 2. `sep` 3. `width`
-            //
-            //  So if the user only provides 2 or less we cannot construct the
-            //  headers so we can safely panic.
-            assert!(
-                expressions.len() > 2,
-                format!(
-                    "expected at least 3 arguments received {}.",
-                    expressions.len()
-                )
-            );
-            //  Collect the expressions into a vector of `syn::ExprAssigns`
-            expressions.into_iter().collect::<Vec<_>>()
-        }
-        Err(_) => {
-            //  Happens whenever the arguments of the `attribute_macro` are
-            //  not well constructed, e.g.
-            //      ```
-            //          #[add_headers(title: "", ...)]
-            //      ```
-            //  will not work because it expects and '=' sign, not a colon.
-            //  Hence it's not a valid assignment.
-            panic!("invalid list of expression arguments");
-        }
-    };
+    //  Consume the argument tokenstream. `Parser::parse2` already returns a
+    //  `syn::Error` pointing at the token that broke parsing (e.g. a `:`
+    //  where an `=` was expected), so we just propagate it as-is instead of
+    //  flattening it into a generic message.
+    let expressions = expr_parser.parse2(args)?;
 
     //  The config object has three maps:
     //
@@ -112,17 +157,39 @@ fn parse_macro_arguments(args: pm2::TokenStream) -> HeaderConfig {
     //       pass, but we don't care about limiting this number because either
     //       way we are only going to use the one's we care about.
     for expr in expressions {
-        //  Store the identifier always as a `String`.
-        let lhs_expr = match *(expr.left) {
+        //  Store the identifier always as a `String`, keeping the original
+        //  `left` expression around so any error can still point at it.
+        let lhs_expr = match &*expr.left {
             syn::Expr::Path(p) => match p.path.get_ident() {
                 Some(res) => res.to_string(),
-                None => panic!("expected identifier, found `path`."),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &expr.left,
+                        "expected identifier, found `path`.",
+                    ))
+                }
             },
-            _ => panic!("expected identifer, found something else."),
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected identifier, found something else.",
+                ))
+            }
         };
 
+        //  Reject anything that isn't one of the keys we actually understand,
+        //  rather than silently ignoring it -- if it's a near-miss of a known
+        //  key (a typo), suggest the one it's probably meant to be.
+        if !KNOWN_KEYS.contains(&lhs_expr.as_str()) {
+            let mut message = format!("unknown argument `{}`.", lhs_expr);
+            if let Some(suggestion) = suggest_known_key(&lhs_expr) {
+                message.push_str(&format!(" did you mean `{}`?", suggestion));
+            }
+            return Err(syn::Error::new_spanned(&expr.left, message));
+        }
+
         //  Match the `rhs` with literals only.
-        let rhs_expr: Opts = match *(expr.right) {
+        let rhs_expr: Opts = match &*expr.right {
             //  The top level match of the `syn::Expr` inside the box
             //  will produce a `syn::ExprLit` which has an element
             //  inside called `lit` which is of type `syn::Lit` which
@@ -135,56 +202,66 @@ fn parse_macro_arguments(args: pm2::TokenStream) -> HeaderConfig {
             //      a) string literals  b) binary literals  c) integer literals
             //
             //  If the user provides something that is not of these three
-            //  types then we can safely panic.
-            syn::Expr::Lit(expr) => match expr.lit {
-                syn::Lit::Str(str_lit) => Opts::Str(str_lit.value()),
+            //  types then we report an error pointing at that literal.
+            syn::Expr::Lit(expr_lit) => match &expr_lit.lit {
+                syn::Lit::Str(str_lit) => Opts::Str(str_lit.value(), str_lit.span()),
                 syn::Lit::Bool(bin_lit) => Opts::Bin(bin_lit.value),
-                syn::Lit::Int(num_lit) => {
-                    Opts::Num(num_lit.base10_digits().parse::<usize>().unwrap())
-                }
-                _ => {
-                    panic!("expected literal of type `bool`, `str` or `num`, found something else.")
+                syn::Lit::Int(num_lit) => Opts::Num(num_lit.base10_parse::<usize>()?),
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "expected literal of type `bool`, `str` or `num`, found something else.",
+                    ))
                 }
             },
-            _ => panic!("expected literal, found something else."),
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected literal, found something else.",
+                ))
+            }
         };
 
+        //  A known key given a value of the wrong literal type (e.g. `title = 5`)
+        //  must not be allowed to silently land in the wrong bucket -- that makes
+        //  a later bucket lookup miss and report the key as entirely absent,
+        //  which is actively misleading when the user did supply it.
+        let expected = expected_type_for(&lhs_expr);
+        let actual = actual_type_of(&rhs_expr);
+        if actual != expected {
+            return Err(syn::Error::new_spanned(
+                &expr.right,
+                format!(
+                    "expected a `{}` literal for `{}`, found `{}`.",
+                    expected, lhs_expr, actual
+                ),
+            ));
+        }
+
         //  Place each literal and it's associated key in it's respective bucket
         //  inside the config object.
         match rhs_expr {
             Opts::Num(num_opt) => config.num_opts.push((lhs_expr, num_opt)),
             Opts::Bin(bin_opt) => config.bin_opts.push((lhs_expr, bin_opt)),
-            Opts::Str(str_opt) => config.str_opts.push((lhs_expr, str_opt)),
+            Opts::Str(str_opt, span) => config.str_opts.push((lhs_expr, str_opt, span)),
         }
     }
 
-    config
-}
-
-use std::{error, fmt};
-
-#[derive(Clone, Debug)]
-struct ArgNotFound<'a> {
-    //  We create this custom error, to store the name of the missing argument.
-    name: &'a str,
-}
-
-impl<'a> fmt::Display for ArgNotFound<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "expected argument with name '{}', found none.",
-            self.name
-        )
-    }
+    Ok(config)
 }
 
-impl<'a> error::Error for ArgNotFound<'a> {}
-
 //  Helper function to help us locate a given argument name within a specified map
 //  in the `HeaderConfig` object. NOTE that we have to take the map `Vec` as ref
 //  and avoid moving values from it cause we might need them later.
-fn find_argument<K, V>(map: &Vec<(K, V)>, arg_name: &'static str) -> Result<V, impl error::Error>
+//
+//  `args_span` is the span of the whole argument list, used to point the
+//  "argument not found" error somewhere sensible since there is no token for
+//  a missing argument to point at.
+fn find_argument<K, V>(
+    map: &[(K, V)],
+    arg_name: &'static str,
+    args_span: pm2::Span,
+) -> syn::Result<V>
 where
     K: PartialEq<str>,
     V: Clone,
@@ -193,122 +270,460 @@ where
     //  the vector it should be located on. Notice that the key is given in the form
     //  of `arg_name` which is of type `&str` so `K` needs to be comparable to a string.
     match map.iter().find(|(k, _)| k == arg_name) {
-        //  If we find the key in the given vector, then we return its ssociated value.
+        //  If we find the key in the given vector, then we return its associated value.
         //  Since, we don't want to move the value from the Vector we are given we need
         //  the value type `V` to implement the `Clone` trait.
         Some((_, val)) => Ok(val.clone()),
-        //  Else we return an error.
-        None => Err(ArgNotFound { name: arg_name }),
+        //  Else we return an error pointing at the argument list.
+        None => Err(syn::Error::new(
+            args_span,
+            format!("expected argument with name '{}', found none.", arg_name),
+        )),
+    }
+}
+
+//  Same idea as `find_argument`, but for `str_opts` specifically: also
+//  returns the span of the string literal the user actually wrote, so a
+//  placeholder error raised later (in `expand_placeholders`) can point at
+//  that literal instead of the whole `#[frame(...)]` argument list.
+fn find_str_argument(
+    map: &[(String, String, pm2::Span)],
+    arg_name: &'static str,
+    args_span: pm2::Span,
+) -> syn::Result<(String, pm2::Span)> {
+    match map.iter().find(|(k, _, _)| k == arg_name) {
+        Some((_, val, span)) => Ok((val.clone(), *span)),
+        None => Err(syn::Error::new(
+            args_span,
+            format!("expected argument with name '{}', found none.", arg_name),
+        )),
+    }
+}
+
+//  A placeholder found inside a `title`/`sep` format string, e.g. the `{n}`
+//  in `"width={n}"`, already resolved to the function parameter it names.
+struct Placeholder {
+    ident: syn::Ident,
+}
+
+//  Scans `raw` for `{...}` placeholders the way Rust's own format strings
+//  do: `{{`/`}}` are literal braces, and anything else between a bare `{`
+//  and `}` is a placeholder made of an identifier followed by an optional
+//  `:`-prefixed format spec (fill/align/width, passed through untouched).
+//  Every identifier must name one of `params`, the function's own argument
+//  list; a placeholder without one (`{}` or `{:>3}`) names no parameter and
+//  is rejected with the same error as an unknown name.
+//
+//  Returns a format string rust's own `format!` can consume (placeholders
+//  rewritten to plain `{}`/`{:spec}`) together with the `syn::Ident`s to
+//  pass as its arguments, in order. `span` is used for every diagnostic
+//  since `proc_macro2::Span` cannot point at a sub-range of a string
+//  literal on stable.
+fn expand_placeholders(
+    raw: &str,
+    span: pm2::Span,
+    params: &[syn::Ident],
+) -> syn::Result<(String, Vec<Placeholder>)> {
+    let mut out = String::with_capacity(raw.len());
+    let mut placeholders = Vec::new();
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push_str("}}");
+            }
+            '}' => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("unmatched `}}` in format string `{}`.", raw),
+                ))
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut spec = String::new();
+                let mut in_spec = false;
+                let mut closed = false;
+                for c in &mut chars {
+                    match c {
+                        '}' => {
+                            closed = true;
+                            break;
+                        }
+                        ':' if !in_spec => in_spec = true,
+                        _ if in_spec => spec.push(c),
+                        _ => name.push(c),
+                    }
+                }
+                if !closed {
+                    return Err(syn::Error::new(
+                        span,
+                        format!("unmatched `{{` in format string `{}`.", raw),
+                    ));
+                }
+                if !params.iter().any(|p| *p == name) {
+                    return Err(syn::Error::new(
+                        span,
+                        format!(
+                            "`{}` is not a parameter of this function, found in `{}`.",
+                            name, raw
+                        ),
+                    ));
+                }
+
+                out.push('{');
+                if in_spec {
+                    out.push(':');
+                    out.push_str(&spec);
+                }
+                out.push('}');
+                placeholders.push(Placeholder {
+                    ident: syn::Ident::new(&name, span),
+                });
+            }
+            other => out.push(other),
+        }
+    }
+
+    Ok((out, placeholders))
+}
+
+//  Turns a format string and the placeholders found in it into an
+//  expression `pm2::TokenStream`: the format string itself when there are
+//  no placeholders (so the generated code is indistinguishable from before
+//  this feature existed), or a `format!(...)` call wired up with the
+//  matching parameter idents otherwise.
+fn format_expr(fmt: &str, placeholders: &[Placeholder]) -> pm2::TokenStream {
+    if placeholders.is_empty() {
+        quote::quote! { #fmt }
+    } else {
+        let idents = placeholders.iter().map(|p| &p.ident);
+        quote::quote! { format!(#fmt, #(#idents),*) }
+    }
+}
+
+//  Same idea as `format_expr`, but repeated `count` times to build a
+//  separator line. When `sep` has no placeholders the repetition happens
+//  right here, at macro-expansion time, exactly like before this feature
+//  existed; otherwise it's deferred to the generated code since `sep`'s
+//  value is only known once the function actually runs.
+fn repeated_sep_expr(sep: &str, placeholders: &[Placeholder], count: usize) -> pm2::TokenStream {
+    if placeholders.is_empty() {
+        let repeated = sep.repeat(count);
+        quote::quote! { #repeated }
+    } else {
+        let idents = placeholders.iter().map(|p| &p.ident);
+        quote::quote! { (format!(#sep, #(#idents),*)).repeat(#count) }
     }
 }
 
+//  The struct/variable names used for a function's RAII footer guard,
+//  derived from the function's own ident so that two distinct framed
+//  functions never collide, even when one is nested inside the other.
+//  The variable is prefixed with `_` so it doesn't trip `unused_variables`
+//  while still running its `Drop` impl at the end of the scope, unlike a
+//  bare `_`, which would drop it immediately.
+fn guard_idents(fn_ident: &syn::Ident) -> (syn::Ident, syn::Ident) {
+    (
+        quote::format_ident!("__FrameGuard_{}", fn_ident),
+        quote::format_ident!("_frame_guard_{}", fn_ident),
+    )
+}
+
+//  Builds the header `println!` (emitted immediately, as the first
+//  statement of the function) and the declaration of a local RAII guard
+//  whose `Drop` impl prints the footer -- and, when `timing` is set, the
+//  elapsed time -- no matter how the function exits: early `return`, `?`,
+//  or even a panic unwinding through it.
+#[allow(clippy::too_many_arguments)]
 fn construct_guards(
     segment_title: String,
+    title_placeholders: Vec<Placeholder>,
     sep: String,
+    sep_placeholders: Vec<Placeholder>,
     width: usize,
     sep_line: bool,
+    timing: bool,
+    fn_ident: &syn::Ident,
 ) -> (pm2::TokenStream, pm2::TokenStream) {
+    //  `segment_title.len()` is the length of the *pattern*, not of whatever
+    //  ends up being printed once its placeholders are filled in at
+    //  runtime -- there is no way to know that length at macro-expansion
+    //  time, so alignment is only best-effort when placeholders are used.
+    let title = format_expr(&segment_title, &title_placeholders);
+
     //  The `sep_line` argument specifies whether the title should be printed in its
     //  own line or in a same line as the separators.
-    if sep_line {
+    let (header, fsep) = if sep_line {
         //  If we want the segment title in it's own line we need to modify the width
         //  given by the user to account for that.
         let width = width + segment_title.len();
-        let hsep = sep.repeat(width);
+        let hsep = repeated_sep_expr(&sep, &sep_placeholders, width);
         let header = quote::quote! {
             //  The blank space in the `format!` macro tells rust to pad the segment
             //  title with whitespace, `width` number of times.
-            println!("{}\n{}\n{}", #hsep, format!("{: ^1$}", #segment_title, #width), #hsep);
+            println!("{}\n{}\n{}", #hsep, format!("{: ^1$}", #title, #width), #hsep);
         };
         //  Constructing the `footer` is pretty much the same as with the header.
-        let fsep = sep.repeat(width);
-        let footer = quote::quote! {
-            println!("{}", #fsep);
-        };
+        let fsep = repeated_sep_expr(&sep, &sep_placeholders, width);
 
-        (header, footer)
+        (header, fsep)
     } else {
         //  Print the header and separators in the same line.
-        let hsep = sep.repeat(width);
+        let hsep = repeated_sep_expr(&sep, &sep_placeholders, width);
         let header = quote::quote! {
-            println!("{} {} {}", #hsep, #segment_title, #hsep);
+            println!("{} {} {}", #hsep, #title, #hsep);
         };
 
-        let fsep = sep.repeat(2 * (width + 1) + segment_title.len());
-        let footer = quote::quote! {
-            println!("{}", #fsep);
-        };
+        let fsep = repeated_sep_expr(&sep, &sep_placeholders, 2 * (width + 1) + segment_title.len());
+
+        (header, fsep)
+    };
 
-        (header, footer)
+    let footer_println = quote::quote! { println!("{}", #fsep); };
+    let (guard_struct, guard_var) = guard_idents(fn_ident);
+
+    //  When `timing` is on, the guard carries the `Instant` it was created
+    //  with so `Drop::drop` can report how long the function ran for.
+    let guard = if timing {
+        quote::quote! {
+            #[allow(non_camel_case_types)]
+            struct #guard_struct(::std::time::Instant);
+            impl ::std::ops::Drop for #guard_struct {
+                fn drop(&mut self) {
+                    #footer_println
+                    println!("elapsed: {:?}", self.0.elapsed());
+                }
+            }
+            let #guard_var = #guard_struct(::std::time::Instant::now());
+        }
+    } else {
+        quote::quote! {
+            #[allow(non_camel_case_types)]
+            struct #guard_struct;
+            impl ::std::ops::Drop for #guard_struct {
+                fn drop(&mut self) {
+                    #footer_println
+                }
+            }
+            let #guard_var = #guard_struct;
+        }
+    };
+
+    (header, guard)
+}
+
+//  Only plain `ident: Type` parameters can be named from a placeholder; patterns
+//  like `(a, b): (u8, u8)` or `self` simply aren't nameable and are skipped.
+fn collect_params(sig: &syn::Signature) -> Vec<syn::Ident> {
+    sig.inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+                syn::Pat::Ident(pat_ident) => Some(pat_ident.ident.clone()),
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+//  Builds the header/guard pair for one function/method, resolving its
+//  `title` (falling back to the function's own name when `explicit_title`
+//  is `None`, which only happens inside an `impl`/`mod`) and expanding
+//  `title`/`sep` placeholders against that function's own parameters.
+//
+//  `explicit_title` and `sep` each carry the span of the string literal the
+//  user actually wrote (when they wrote one), so a bad placeholder inside
+//  either one is reported at that literal rather than at `args_span`.
+#[allow(clippy::too_many_arguments)]
+fn build_guards_for_fn(
+    sig: &syn::Signature,
+    explicit_title: Option<(&str, pm2::Span)>,
+    sep: &str,
+    sep_span: pm2::Span,
+    width: usize,
+    sep_line: bool,
+    timing: bool,
+) -> syn::Result<(pm2::TokenStream, pm2::TokenStream)> {
+    let params = collect_params(sig);
+
+    let (title, title_span) = explicit_title
+        .map(|(title, span)| (title.to_string(), span))
+        .unwrap_or_else(|| (sig.ident.to_string(), sig.ident.span()));
+    let (title, title_placeholders) = expand_placeholders(&title, title_span, &params)?;
+    let (sep, sep_placeholders) = expand_placeholders(sep, sep_span, &params)?;
+
+    Ok(construct_guards(
+        title,
+        title_placeholders,
+        sep,
+        sep_placeholders,
+        width,
+        sep_line,
+        timing,
+        &sig.ident,
+    ))
+}
+
+//  Parses `header` into a `syn::Stmt` and `guard` (the RAII footer guard's
+//  struct/impl/let) into the handful of `syn::Stmt`s it's made of, then
+//  splices both in right at the top of `block` -- the guard immediately
+//  after the header, so its `Drop` fires on every exit path through the
+//  rest of the body, including an early `return`, a propagated `?`, or an
+//  unwinding panic.
+fn insert_guards(
+    block: &mut syn::Block,
+    header: pm2::TokenStream,
+    guard: pm2::TokenStream,
+) -> syn::Result<()> {
+    let header_stmt = syn::Stmt::parse.parse2(header)?;
+    let guard_stmts = syn::Block::parse_within.parse2(guard)?;
+
+    block.stmts.insert(0, header_stmt);
+    for (offset, stmt) in guard_stmts.into_iter().enumerate() {
+        block.stmts.insert(1 + offset, stmt);
     }
+    Ok(())
 }
 
-#[proc_macro_attribute]
-pub fn frame(args: pm::TokenStream, item: pm::TokenStream) -> pm::TokenStream {
-    //  Change the input to `proc_macro2::TokenStream` as `syn` and `quote` both
-    //  work with this type of `TokenStream`, and it allows for compiler version
-    //  independent code, and allows the code to exist outside the macro compila-
-    //  tion level -- which means you can unit test it.
-    let args = pm2::TokenStream::from(args.clone());
+fn frame_impl(args: pm2::TokenStream, item: pm2::TokenStream) -> syn::Result<pm2::TokenStream> {
+    //  The span of the whole argument list, used to point at something
+    //  sensible when `title` is missing entirely, and for placeholder errors
+    //  in a `sep` that fell back to its default.
+    let args_span = args.span();
+
     //  Get the config object from the arguments passed by the user.
-    let conf = parse_macro_arguments(args);
+    let conf = parse_macro_arguments(args)?;
 
-    let mut segment_title = match find_argument(&conf.str_opts, "title") {
-        Ok(title) => title,
-        Err(err) => panic!(format!("{}\nmake sure teh value is of type `str`.", err)),
+    //  `title` is mandatory on a bare function -- there's no enclosing name to
+    //  fall back on -- but becomes optional on an `impl`/`mod`, where each
+    //  function gets its own name as a default title instead.
+    let explicit_title = match find_str_argument(&conf.str_opts, "title", args_span) {
+        Ok((mut title, span)) => {
+            //  For some reason the `"` character seems to be part of the `syn::Lit`
+            //  type so even after we convert it to a string, we get something that
+            //  is wrapped in quotes, which in this case is undesirable.
+            title.retain(|c| c != '\"');
+            Some((title, span))
+        }
+        Err(_) => None,
     };
 
-    //  For some reason the `"` character seems to be part of the `syn::Lit` type
-    //  so even after we convert it to a string, we get something that is wrapped
-    //  in quotes, which in this case is undersirable.
-    segment_title.retain(|c| c != '\"');
-
-    //  The separating character or string.
-    let mut sep = match find_argument(&conf.str_opts, "sep") {
-        Ok(sep) => sep,
-        Err(err) => panic!(format!("{}\nmake sure the value is of type `str`.", err)),
-    };
+    //  NOTE these four `unwrap_or`s only ever fire because the key was never
+    //       supplied at all: `parse_macro_arguments` already rejects a known
+    //       key given a value of the wrong literal type (e.g. `width = "ten"`)
+    //       with a compile error before it can reach the matching bucket here,
+    //       so a bucket miss at this point unambiguously means "absent", never
+    //       "present but wrong type".
 
+    //  The separating character or string. Optional, defaults to `DEFAULT_SEP`,
+    //  in which case there's no literal to point at so placeholder errors in
+    //  it fall back to `args_span`.
+    let (mut sep, sep_span) =
+        find_str_argument(&conf.str_opts, "sep", args_span).unwrap_or_else(|_| (DEFAULT_SEP.to_string(), args_span));
     sep.retain(|c| c != '\"');
 
     //  The number of times you want the separator character to be repeated.
-    let width = match find_argument(&conf.num_opts, "width") {
-        Ok(width) => width,
-        Err(err) => panic!(format!("{}\nmake sure the value is of type `usize`.", err)),
-    };
+    //  Optional, defaults to `DEFAULT_WIDTH`.
+    let width = find_argument(&conf.num_opts, "width", args_span).unwrap_or(DEFAULT_WIDTH);
 
-    //  NOTE this argument is really not that important in order to construct a header,
-    //       so we can make it optional. notice there's no panic if the `find_argument`
-    //       function returns an error.
-    let sep_line = match find_argument(&conf.bin_opts, "sep_line") {
-        Ok(sep_line) => sep_line,
-        Err(_) => true,
-    };
+    //  This argument is really not that important in order to construct a header,
+    //  so we can make it optional.
+    let sep_line = find_argument(&conf.bin_opts, "sep_line", args_span).unwrap_or(true);
+
+    //  Optional, defaults to `false`. When set, the footer also reports how
+    //  long the function ran for.
+    let timing = find_argument(&conf.bin_opts, "timing", args_span).unwrap_or(false);
 
-    //  Construct two `pm2::TokenStreams` using the `quote` crate.
-    let (header, footer) = construct_guards(segment_title, sep, width, sep_line);
-
-    //  Use `syn::Stmt::parse` function to parse the `pm2::TokenStreams` into `syn::Stmts`
-    //  which makes it much more convenient to insert into the user's code.
-    let macro_parser = syn::Stmt::parse;
-    let header_macro_stmt = Parser::parse2(macro_parser, header).unwrap();
-    let footer_macro_stmt = Parser::parse2(macro_parser, footer).unwrap();
-
-    //  Finally we need to parse the input in order to determine someone is not calling this
-    //  macro in a context where it doesn't make sense. Right now, this macro expects to be
-    //  used only in functions.
-    let input = pm2::TokenStream::from(item.clone());
-    match Parser::parse2(syn::ItemFn::parse, input) {
-        Ok(mut func) => {
-            //  The `func.block.stmts` variable is of type `Vec<syn::Stmts>` so we can easily
-            //  insert our header and footer guards without even having to touch the user's
-            //  existing code.
-            let n = func.block.stmts.len() + 1;
-            func.block.stmts.insert(0, header_macro_stmt);
-            func.block.stmts.insert(n, footer_macro_stmt);
-            //  Finally now that everything is properly setup, we return the modified function.
-            pm::TokenStream::from(func.to_token_stream())
+    //  `#[frame]` accepts three shapes: a single function, an `impl` block
+    //  (every method gets its own guards), or an inline `mod` (every free
+    //  function inside does). `syn` gives no way to peek at which one we
+    //  have without committing to a parse, so we just try each in turn.
+    if let Ok(mut func) = syn::ItemFn::parse.parse2(item.clone()) {
+        let (title, title_span) = explicit_title.ok_or_else(|| {
+            syn::Error::new(
+                args_span,
+                "expected argument with name 'title', found none.\nmake sure the value is of type `str`.",
+            )
+        })?;
+        let (header, guard) = build_guards_for_fn(
+            &func.sig,
+            Some((title.as_str(), title_span)),
+            &sep,
+            sep_span,
+            width,
+            sep_line,
+            timing,
+        )?;
+        insert_guards(&mut func.block, header, guard)?;
+        return Ok(func.to_token_stream());
+    }
+
+    if let Ok(mut item_impl) = syn::ItemImpl::parse.parse2(item.clone()) {
+        for impl_item in item_impl.items.iter_mut() {
+            if let syn::ImplItem::Method(method) = impl_item {
+                let (header, guard) = build_guards_for_fn(
+                    &method.sig,
+                    explicit_title.as_ref().map(|(title, span)| (title.as_str(), *span)),
+                    &sep,
+                    sep_span,
+                    width,
+                    sep_line,
+                    timing,
+                )?;
+                insert_guards(&mut method.block, header, guard)?;
+            }
         }
-        Err(_) => panic!("macro can only be applied to `function` items."),
+        return Ok(item_impl.to_token_stream());
+    }
+
+    if let Ok(mut item_mod) = syn::ItemMod::parse.parse2(item.clone()) {
+        if let Some((_, items)) = item_mod.content.as_mut() {
+            for inner_item in items.iter_mut() {
+                if let syn::Item::Fn(func) = inner_item {
+                    let (header, guard) = build_guards_for_fn(
+                        &func.sig,
+                        explicit_title.as_ref().map(|(title, span)| (title.as_str(), *span)),
+                        &sep,
+                        sep_span,
+                        width,
+                        sep_line,
+                        timing,
+                    )?;
+                    insert_guards(&mut func.block, header, guard)?;
+                }
+            }
+        }
+        return Ok(item_mod.to_token_stream());
+    }
+
+    Err(syn::Error::new(
+        item.span(),
+        "`#[frame]` can only be applied to a function, an `impl` block, or an inline `mod`.",
+    ))
+}
+
+#[proc_macro_attribute]
+pub fn frame(args: pm::TokenStream, item: pm::TokenStream) -> pm::TokenStream {
+    //  Change the input to `proc_macro2::TokenStream` as `syn` and `quote` both
+    //  work with this type of `TokenStream`, and it allows for compiler version
+    //  independent code, and allows the code to exist outside the macro compila-
+    //  tion level -- which means you can unit test it.
+    let args = pm2::TokenStream::from(args);
+    let item = pm2::TokenStream::from(item);
+
+    //  Rather than aborting with a bare `panic!` (which the compiler reports as
+    //  an ugly backtrace with no source location), turn every failure in the
+    //  pipeline into a `syn::Error` that keeps the span of the offending token
+    //  and emit it as a normal compile error.
+    match frame_impl(args, item) {
+        Ok(tokens) => pm::TokenStream::from(tokens),
+        Err(err) => pm::TokenStream::from(err.to_compile_error()),
     }
 }